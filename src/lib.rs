@@ -1,73 +1,749 @@
 // use std::collections::HashMap;
-use fnv::FnvHashMap as HashMap;
-use std::{hash::Hash, collections::VecDeque};
+use arrayvec::ArrayVec;
+use fnv::{FnvBuildHasher, FnvHashMap as HashMap};
+use hashbrown::raw::RawTable;
+use std::hash::{BuildHasher, Hash};
+use std::time::Instant;
 
-trait LRUCache<K, V> {
+/// a monotonic tick source; returns an ever-increasing count (ms by default)
+type Clock = Box<dyn Fn() -> u64>;
+
+/// default clock: milliseconds elapsed since the cache was created
+fn default_clock() -> Clock {
+    let start = Instant::now();
+    Box::new(move || start.elapsed().as_millis() as u64)
+}
+
+/// sentinel slot index standing in for a null `prev`/`next` link
+const NIL: u32 = u32::MAX;
+
+/// eviction policy for [`Cache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// evict the least-recently-used entry; `get` promotes to MRU
+    Lru,
+    /// evict the oldest-inserted entry; `get` does not reorder
+    Fifo,
+}
+
+pub trait LRUCache<K, V> {
     fn initialize(max: u16) -> Self;
     fn get(&mut self, k: K) -> Option<V>;
     fn set(&mut self, k: K, v: V);
 }
 
+/// a single entry in the intrusive recency list
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    /// slot index of the less-recently-used neighbour (`NIL` at the head)
+    prev: u32,
+    /// slot index of the more-recently-used neighbour (`NIL` at the tail)
+    next: u32,
+    /// tick at which this entry was inserted or last refreshed
+    stamp: u64,
+}
+
 pub struct Cache<K: Eq + Hash, V> {
-    /// generation index
-    g: u32,
     /// max capacity of the cache
     max: u16,
-    /// cached items
-    items: HashMap<K, (V, u32)>,
-    recency_buckets: VecDeque<(u32, K)>,
+    /// set of occupied slots, stored as indices into `slab`; the key itself
+    /// lives only in the node, reached through the hash/eq closures
+    table: RawTable<u32>,
+    /// hasher used to key `table`
+    hasher: FnvBuildHasher,
+    /// node storage, slots are recycled through `free`
+    slab: Vec<Node<K, V>>,
+    /// head of the free list, or `NIL` when there are no recycled slots
+    free: u32,
+    /// least-recently-used end of the list (next to evict)
+    head: u32,
+    /// most-recently-used end of the list
+    tail: u32,
+    /// eviction policy governing whether `get` reorders recency
+    policy: Policy,
+    /// optional time-to-live for entries, in clock ticks
+    ttl: Option<u64>,
+    /// monotonic clock source, swappable for deterministic tests
+    clock: Clock,
+    /// optional on-disk spill tier for entries evicted from memory
+    #[cfg(feature = "disk")]
+    spill: Option<spill::BucketStore>,
 }
 
-impl<'a, K: Eq + Hash + Clone, V: Copy> LRUCache<K, V> for Cache<K, V> {
+impl<K: Eq + Hash, V> Cache<K, V> {
+    /// hash a borrowed key with the table's hasher
+    fn hash_of(&self, k: &K) -> u64 {
+        self.hasher.hash_one(k)
+    }
+
+    /// detach `idx` from the recency list, patching its neighbours
+    fn unlink(&mut self, idx: u32) {
+        let (prev, next) = {
+            let node = &self.slab[idx as usize];
+            (node.prev, node.next)
+        };
+        if prev != NIL {
+            self.slab[prev as usize].next = next;
+        } else {
+            self.head = next;
+        }
+        if next != NIL {
+            self.slab[next as usize].prev = prev;
+        } else {
+            self.tail = prev;
+        }
+    }
+
+    /// splice `idx` in at the MRU (tail) end of the recency list
+    fn push_tail(&mut self, idx: u32) {
+        let old_tail = self.tail;
+        {
+            let node = &mut self.slab[idx as usize];
+            node.prev = old_tail;
+            node.next = NIL;
+        }
+        if old_tail != NIL {
+            self.slab[old_tail as usize].next = idx;
+        } else {
+            self.head = idx;
+        }
+        self.tail = idx;
+    }
+
+    /// unlink `idx`, drop its key from the table, and recycle the slot
+    fn remove_slot(&mut self, idx: u32) {
+        self.unlink(idx);
+        let hash = self.hash_of(&self.slab[idx as usize].key);
+        if let Some(bucket) = self.table.find(hash, |&i| i == idx) {
+            unsafe {
+                self.table.erase(bucket);
+            }
+        }
+        self.slab[idx as usize].next = self.free;
+        self.free = idx;
+    }
+
+    /// find the slot holding `k`, if any
+    fn lookup(&self, hash: u64, k: &K) -> Option<u32> {
+        let slab = &self.slab;
+        self.table
+            .get(hash, |&i| slab[i as usize].key == *k)
+            .copied()
+    }
+
+    /// resolve `k` to a live slot, expiring and reporting a miss when stale
+    fn resolve(&mut self, hash: u64, k: &K) -> Option<u32> {
+        let idx = self.lookup(hash, k)?;
+        if let Some(ttl) = self.ttl {
+            if (self.clock)().saturating_sub(self.slab[idx as usize].stamp) > ttl {
+                self.remove_slot(idx);
+                return None;
+            }
+        }
+        Some(idx)
+    }
+
+    /// store `(k, v)` in a fresh slot at the MRU end, stamping it `now`
+    fn install(&mut self, k: K, v: V, now: u64, hash: u64) {
+        let idx = if self.free != NIL {
+            let idx = self.free;
+            let node = &mut self.slab[idx as usize];
+            self.free = node.next;
+            node.key = k;
+            node.value = v;
+            node.stamp = now;
+            idx
+        } else {
+            let idx = self.slab.len() as u32;
+            self.slab.push(Node {
+                key: k,
+                value: v,
+                prev: NIL,
+                next: NIL,
+                stamp: now,
+            });
+            idx
+        };
+        self.push_tail(idx);
+
+        let slab = &self.slab;
+        let hasher = &self.hasher;
+        self.table
+            .insert(hash, idx, |&i| hasher.hash_one(&slab[i as usize].key));
+    }
+}
+
+impl<K: Eq + Hash, V: Copy> LRUCache<K, V> for Cache<K, V> {
     fn initialize(max: u16) -> Self {
         Self {
-            g: 0,
             max,
-            items: HashMap::<K, (V, u32)>::with_capacity_and_hasher(
-                max as usize,
-                Default::default(),
-            ),
-            recency_buckets: VecDeque::with_capacity(max as usize),
+            table: RawTable::with_capacity(max as usize),
+            hasher: FnvBuildHasher::default(),
+            slab: Vec::with_capacity(max as usize),
+            free: NIL,
+            head: NIL,
+            tail: NIL,
+            policy: Policy::Lru,
+            ttl: None,
+            clock: default_clock(),
+            #[cfg(feature = "disk")]
+            spill: None,
         }
     }
     fn get(&mut self, k: K) -> Option<V> {
-        let entry = self.items.get_mut(&k);
-        if let Some(entry) = entry {
-            let recency = self.g + 1;
-
-            // update recency info for the key
-            // keeping it sorted allows O(1) expiry later
-            if let Ok(idx) = self.recency_buckets.binary_search_by(|(g, _k)| g.cmp(&entry.1)) {
-                if idx == 0 {
-                    let _ = self.recency_buckets.pop_front();
-                } else {
-                    // worst case is (O(N / 2)) since it shift on the shorter side
-                    self.recency_buckets.remove(idx);
+        let hash = self.hash_of(&k);
+        let idx = self.resolve(hash, &k)?;
+        // under LRU, touch: move the node to the MRU end in O(1). FIFO leaves
+        // insertion order untouched so reads stay cheap and eviction stable.
+        if self.policy == Policy::Lru {
+            self.unlink(idx);
+            self.push_tail(idx);
+        }
+        Some(self.slab[idx as usize].value)
+    }
+    fn set(&mut self, k: K, v: V) {
+        let now = (self.clock)();
+        let hash = self.hash_of(&k);
+        if let Some(idx) = self.lookup(hash, &k) {
+            // present already: just refresh its freshness stamp
+            self.slab[idx as usize].stamp = now;
+            return;
+        }
+        // evict the LRU entry and recycle its slot
+        if self.table.len() + 1 > self.max as usize {
+            self.remove_slot(self.head);
+        }
+        self.install(k, v, now, hash);
+    }
+}
+
+impl<K: Eq + Hash, V: Copy> Cache<K, V> {
+    /// construct a cache using the given eviction `policy`
+    pub fn initialize_with_policy(max: u16, policy: Policy) -> Self {
+        let mut cache = Self::initialize(max);
+        cache.policy = policy;
+        cache
+    }
+
+    /// construct a cache that also drops entries older than `ttl` ticks
+    pub fn initialize_with_ttl(max: u16, ttl: u64) -> Self {
+        let mut cache = Self::initialize(max);
+        cache.ttl = Some(ttl);
+        cache
+    }
+
+    /// swap in a custom clock source (mostly useful for deterministic tests)
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// drop every entry that has outlived the ttl, sweeping from the LRU end
+    /// until a still-fresh entry is reached
+    pub fn purge_expired(&mut self) {
+        let ttl = match self.ttl {
+            Some(ttl) => ttl,
+            None => return,
+        };
+        let now = (self.clock)();
+        let mut idx = self.head;
+        // the list is ordered by access (LRU) or by insertion (FIFO), neither
+        // of which tracks the refresh `stamp` once `set` restamps in place, so
+        // scan the whole list rather than stopping at the first fresh entry
+        while idx != NIL {
+            let next = self.slab[idx as usize].next;
+            if now.saturating_sub(self.slab[idx as usize].stamp) > ttl {
+                self.remove_slot(idx);
+            }
+            idx = next;
+        }
+    }
+}
+
+/// default number of on-disk spill buckets (a power of two)
+#[cfg(feature = "disk")]
+const DEFAULT_BUCKETS: usize = 256;
+
+#[cfg(feature = "disk")]
+impl<K, V> Cache<K, V>
+where
+    K: Eq + std::hash::Hash + serde::Serialize,
+    V: Copy + serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// construct a two-tier cache: entries evicted from the `max_mem`-entry
+    /// memory tier spill to bucket files under `bucket_dir` rather than being
+    /// discarded, and a memory miss transparently promotes a cold entry back
+    pub fn initialize_tiered(
+        max_mem: u16,
+        bucket_dir: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<Self> {
+        let mut cache = <Self as LRUCache<K, V>>::initialize(max_mem);
+        cache.spill = Some(spill::BucketStore::new(bucket_dir, DEFAULT_BUCKETS)?);
+        Ok(cache)
+    }
+
+    /// spill-aware read: memory first, then the bucket store (promoting a hit
+    /// back into memory). Distinct from the trait `get` so value types that are
+    /// not serialisable keep working with the `disk` feature enabled.
+    pub fn get_tiered(&mut self, k: K) -> Option<V> {
+        let hash = self.hash_of(&k);
+        if let Some(idx) = self.lookup(hash, &k) {
+            // present in memory: honour the ttl here and do NOT fall through to
+            // the spill tier, which could otherwise serve an older spilled copy
+            if let Some(ttl) = self.ttl {
+                if (self.clock)().saturating_sub(self.slab[idx as usize].stamp) > ttl {
+                    self.remove_slot(idx);
+                    return None;
+                }
+            }
+            if self.policy == Policy::Lru {
+                self.unlink(idx);
+                self.push_tail(idx);
+            }
+            return Some(self.slab[idx as usize].value);
+        }
+        // cold: look in the spill tier and promote on hit
+        let hit = self.spill.as_ref().and_then(|store| store.get::<K, V>(&k));
+        if let Some(v) = hit {
+            self.set_tiered(k, v);
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// spill-aware write: an eviction is written to its bucket before the slot
+    /// is recycled. Distinct from the trait `set` for the same reason as
+    /// [`Cache::get_tiered`].
+    pub fn set_tiered(&mut self, k: K, v: V) {
+        let now = (self.clock)();
+        let hash = self.hash_of(&k);
+        if let Some(idx) = self.lookup(hash, &k) {
+            self.slab[idx as usize].stamp = now;
+            return;
+        }
+        if self.table.len() + 1 > self.max as usize {
+            let evict = self.head;
+            if let Some(store) = self.spill.as_mut() {
+                let node = &self.slab[evict as usize];
+                // a failed spill must not wedge the in-memory cache
+                let _ = store.put(&node.key, &node.value);
+            }
+            self.remove_slot(evict);
+        }
+        self.install(k, v, now, hash);
+    }
+}
+
+/// on-disk spill tier: a fixed, power-of-two set of mmap-backed bucket files.
+#[cfg(feature = "disk")]
+mod spill {
+    use fnv::FnvBuildHasher;
+    use memmap2::MmapMut;
+    use serde::{de::DeserializeOwned, Serialize};
+    use std::fs::{File, OpenOptions};
+    use std::hash::{BuildHasher, Hash};
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    /// initial byte capacity of a freshly created bucket file
+    const INITIAL_CAP: usize = 4096;
+    /// bytes at the start of each bucket recording the occupied length
+    const HEADER: usize = 8;
+
+    pub struct BucketStore {
+        mask: u64,
+        hasher: FnvBuildHasher,
+        buckets: Vec<Bucket>,
+    }
+
+    struct Bucket {
+        file: File,
+        map: MmapMut,
+        /// bytes of the map currently occupied by appended records
+        used: usize,
+    }
+
+    impl BucketStore {
+        /// open `num_buckets` (rounded up to a power of two) bucket files in
+        /// `dir`, creating the directory if necessary
+        pub fn new(dir: impl AsRef<Path>, num_buckets: usize) -> io::Result<Self> {
+            let dir = dir.as_ref();
+            std::fs::create_dir_all(dir)?;
+            let n = num_buckets.next_power_of_two().max(1);
+            let mut buckets = Vec::with_capacity(n);
+            for i in 0..n {
+                let path: PathBuf = dir.join(format!("bucket_{i}"));
+                buckets.push(Bucket::open(&path)?);
+            }
+            Ok(Self {
+                mask: (n as u64) - 1,
+                hasher: FnvBuildHasher::default(),
+                buckets,
+            })
+        }
+
+        fn bucket_of<K: Hash>(&self, k: &K) -> usize {
+            (self.hasher.hash_one(k) & self.mask) as usize
+        }
+
+        /// append `(k, v)` to the bucket selected by `k`
+        pub fn put<K: Serialize + Hash, V: Serialize>(&mut self, k: &K, v: &V) -> io::Result<()> {
+            let key = bincode::serialize(k).map_err(to_io)?;
+            let val = bincode::serialize(v).map_err(to_io)?;
+            let b = self.bucket_of(k);
+            self.buckets[b].append(&key, &val)
+        }
+
+        /// return the most recently stored value for `k`, if present
+        pub fn get<K: Serialize + Hash, V: DeserializeOwned>(&self, k: &K) -> Option<V> {
+            let key = bincode::serialize(k).ok()?;
+            let b = self.bucket_of(k);
+            self.buckets[b].lookup(&key)
+        }
+    }
+
+    impl Bucket {
+        fn open(path: &Path) -> io::Result<Self> {
+            // reopen without truncating so previously spilled entries survive
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)?;
+            let len = file.metadata()?.len() as usize;
+            let cap = len.max(INITIAL_CAP);
+            file.set_len(cap as u64)?;
+            let mut map = unsafe { MmapMut::map_mut(&file)? };
+            // the occupied length is persisted in the header, so reopening is
+            // exact and independent of record contents (zero-length keys incl.)
+            let stored = u64::from_le_bytes(map[0..HEADER].try_into().unwrap()) as usize;
+            let used = if (HEADER..=cap).contains(&stored) {
+                stored
+            } else {
+                // fresh (or never-written) file: initialise the header
+                map[0..HEADER].copy_from_slice(&(HEADER as u64).to_le_bytes());
+                HEADER
+            };
+            Ok(Self { file, map, used })
+        }
+
+        /// ensure the map can hold `extra` more bytes, growing to the next
+        /// power-of-two capacity and remapping when it cannot
+        fn reserve(&mut self, extra: usize) -> io::Result<()> {
+            let needed = self.used + extra;
+            if needed <= self.map.len() {
+                return Ok(());
+            }
+            let cap = needed.next_power_of_two();
+            self.file.set_len(cap as u64)?;
+            self.map = unsafe { MmapMut::map_mut(&self.file)? };
+            Ok(())
+        }
+
+        /// append a length-prefixed `(key, value)` record after the header
+        fn append(&mut self, key: &[u8], val: &[u8]) -> io::Result<()> {
+            let rec = 8 + key.len() + val.len();
+            self.reserve(rec)?;
+            let mut off = self.used;
+            self.map[off..off + 4].copy_from_slice(&(key.len() as u32).to_le_bytes());
+            off += 4;
+            self.map[off..off + key.len()].copy_from_slice(key);
+            off += key.len();
+            self.map[off..off + 4].copy_from_slice(&(val.len() as u32).to_le_bytes());
+            off += 4;
+            self.map[off..off + val.len()].copy_from_slice(val);
+            self.used += rec;
+            // keep the persisted length in step with the appended data
+            self.map[0..HEADER].copy_from_slice(&(self.used as u64).to_le_bytes());
+            Ok(())
+        }
+
+        /// scan records, returning the value of the last one whose key matches
+        fn lookup<V: DeserializeOwned>(&self, key: &[u8]) -> Option<V> {
+            let mut off = HEADER;
+            let mut found: Option<V> = None;
+            while off + 8 <= self.used {
+                let klen = u32::from_le_bytes(self.map[off..off + 4].try_into().ok()?) as usize;
+                off += 4;
+                let k = &self.map[off..off + klen];
+                off += klen;
+                let vlen = u32::from_le_bytes(self.map[off..off + 4].try_into().ok()?) as usize;
+                off += 4;
+                let v = &self.map[off..off + vlen];
+                off += vlen;
+                if k == key {
+                    // keep scanning: a later record supersedes an earlier one
+                    found = bincode::deserialize(v).ok();
                 }
             }
-            self.recency_buckets.push_back((recency, k));
+            found
+        }
+    }
 
-            entry.1 = recency;
-            self.g = recency;
+    fn to_io(e: bincode::Error) -> io::Error {
+        io::Error::other(e)
+    }
+}
+
+/// A fixed-capacity LRU cache that lives entirely on the stack.
+///
+/// For tiny caches (a handful of entries) a linear scan over an inline array
+/// beats the hash map + index list machinery on both latency and memory: there
+/// is no allocation and no hashing. The most-recently-used entry sits at index
+/// `0`; `get` moves a hit to the front and `set` pushes at the front, dropping
+/// the last (LRU) entry once the array is full.
+pub struct ArrayCache<K, V, const N: usize> {
+    buf: ArrayVec<(K, V), N>,
+}
 
-            Some(entry.0)
+impl<K: Eq, V: Copy, const N: usize> LRUCache<K, V> for ArrayCache<K, V, N> {
+    /// `max` is ignored; the capacity is the const parameter `N`
+    fn initialize(_max: u16) -> Self {
+        Self {
+            buf: ArrayVec::new(),
+        }
+    }
+    fn get(&mut self, k: K) -> Option<V> {
+        if let Some(pos) = self.buf.iter().position(|(ek, _)| *ek == k) {
+            if pos != 0 {
+                let entry = self.buf.remove(pos);
+                self.buf.insert(0, entry);
+            }
+            Some(self.buf[0].1)
         } else {
             None
         }
     }
     fn set(&mut self, k: K, v: V) {
-        if self.items.contains_key(&k) {
-            return
+        if let Some(pos) = self.buf.iter().position(|(ek, _)| *ek == k) {
+            // refresh an existing key by re-seating it at the front
+            self.buf.remove(pos);
+        } else if self.buf.len() == N {
+            // drop the least-recently-used entry to make room
+            self.buf.pop();
+        }
+        self.buf.insert(0, (k, v));
+    }
+}
+
+/// list ids for the four ARC lists, used to index `head`/`tail`/`len`
+const T1: usize = 0;
+const T2: usize = 1;
+const B1: usize = 2;
+const B2: usize = 3;
+
+/// a node in the ARC slab; ghost entries (B1/B2) carry no `value`
+#[derive(Debug)]
+struct ArcNode<K, V> {
+    key: K,
+    value: Option<V>,
+    prev: u32,
+    next: u32,
+    /// which of `T1`/`T2`/`B1`/`B2` this slot currently belongs to
+    list: u8,
+}
+
+/// Adaptive Replacement Cache.
+///
+/// Keeps two resident lists (T1 for pages seen once, T2 for pages seen at
+/// least twice) and two ghost lists (B1/B2) recording keys recently evicted
+/// from T1/T2. The target `p` splits capacity between T1 and T2 and adapts to
+/// the access pattern so mixed recency/frequency workloads stop thrashing.
+pub struct ArcCache<K: Eq + Hash, V> {
+    /// resident capacity
+    c: usize,
+    /// adaptive target size for T1 (0..=c)
+    p: usize,
+    /// maps a key to its slot, for both resident and ghost entries
+    map: HashMap<K, u32>,
+    /// node storage shared across all four lists, slots recycled via `free`
+    slab: Vec<ArcNode<K, V>>,
+    /// head of the free list, or `NIL`
+    free: u32,
+    /// per-list LRU ends (next to evict)
+    head: [u32; 4],
+    /// per-list MRU ends
+    tail: [u32; 4],
+    /// per-list lengths
+    len: [usize; 4],
+}
+
+impl<K: Eq + Hash + Clone, V: Copy> ArcCache<K, V> {
+    pub fn initialize_arc(max: u16) -> Self {
+        Self {
+            c: max as usize,
+            p: 0,
+            // ghost keys can push the map to 2c entries
+            map: HashMap::with_capacity_and_hasher(2 * max as usize, Default::default()),
+            slab: Vec::with_capacity(2 * max as usize),
+            free: NIL,
+            head: [NIL; 4],
+            tail: [NIL; 4],
+            len: [0; 4],
+        }
+    }
+
+    /// detach `idx` from its current list, patching neighbours and length
+    fn detach(&mut self, idx: u32) {
+        let (prev, next, list) = {
+            let node = &self.slab[idx as usize];
+            (node.prev, node.next, node.list as usize)
+        };
+        if prev != NIL {
+            self.slab[prev as usize].next = next;
+        } else {
+            self.head[list] = next;
+        }
+        if next != NIL {
+            self.slab[next as usize].prev = prev;
+        } else {
+            self.tail[list] = prev;
+        }
+        self.len[list] -= 1;
+    }
+
+    /// splice `idx` in at the MRU end of `list`, tagging its membership
+    fn attach_tail(&mut self, idx: u32, list: usize) {
+        let old = self.tail[list];
+        {
+            let node = &mut self.slab[idx as usize];
+            node.prev = old;
+            node.next = NIL;
+            node.list = list as u8;
+        }
+        if old != NIL {
+            self.slab[old as usize].next = idx;
+        } else {
+            self.head[list] = idx;
+        }
+        self.tail[list] = idx;
+        self.len[list] += 1;
+    }
+
+    /// move a node from wherever it is to the MRU end of `list`
+    fn move_to(&mut self, idx: u32, list: usize) {
+        self.detach(idx);
+        self.attach_tail(idx, list);
+    }
+
+    /// claim a slot for `(key, value)`, reusing a recycled one when available
+    fn alloc(&mut self, key: K, value: Option<V>) -> u32 {
+        if self.free != NIL {
+            let idx = self.free;
+            let node = &mut self.slab[idx as usize];
+            self.free = node.next;
+            node.key = key;
+            node.value = value;
+            idx
+        } else {
+            let idx = self.slab.len() as u32;
+            self.slab.push(ArcNode {
+                key,
+                value,
+                prev: NIL,
+                next: NIL,
+                list: 0,
+            });
+            idx
+        }
+    }
+
+    /// return a detached slot to the free list
+    fn recycle(&mut self, idx: u32) {
+        self.slab[idx as usize].next = self.free;
+        self.free = idx;
+    }
+
+    /// drop the LRU ghost of `list` (B1/B2) entirely, freeing its slot
+    fn drop_ghost(&mut self, list: usize) {
+        let idx = self.head[list];
+        self.detach(idx);
+        let key = &self.slab[idx as usize].key;
+        self.map.remove(key);
+        self.recycle(idx);
+    }
+
+    /// evict a resident victim, moving its key to the matching ghost list
+    fn replace(&mut self, in_b2: bool) {
+        if self.len[T1] >= 1 && (self.len[T1] > self.p || (in_b2 && self.len[T1] == self.p)) {
+            let idx = self.head[T1];
+            self.slab[idx as usize].value = None;
+            self.move_to(idx, B1);
+        } else {
+            let idx = self.head[T2];
+            self.slab[idx as usize].value = None;
+            self.move_to(idx, B2);
+        }
+    }
+
+    pub fn get(&mut self, k: K) -> Option<V> {
+        if let Some(&idx) = self.map.get(&k) {
+            let list = self.slab[idx as usize].list as usize;
+            if list == T1 || list == T2 {
+                // hit: a resident page seen again is promoted to T2
+                self.move_to(idx, T2);
+                return self.slab[idx as usize].value;
+            }
         }
+        None
+    }
 
-        // evict
-        if self.items.len() + 1 > self.max as usize {
-            let evict = self.recency_buckets.pop_front().unwrap();
-            self.items.remove(&evict.1);
+    pub fn set(&mut self, k: K, v: V) {
+        if let Some(&idx) = self.map.get(&k) {
+            match self.slab[idx as usize].list as usize {
+                T1 | T2 => {
+                    // already resident: refresh value and promote to T2
+                    self.slab[idx as usize].value = Some(v);
+                    self.move_to(idx, T2);
+                }
+                B1 => {
+                    let delta = (self.len[B2] / self.len[B1].max(1)).max(1);
+                    self.p = (self.p + delta).min(self.c);
+                    self.replace(false);
+                    self.slab[idx as usize].value = Some(v);
+                    self.move_to(idx, T2);
+                }
+                _ => {
+                    let delta = (self.len[B1] / self.len[B2].max(1)).max(1);
+                    self.p = self.p.saturating_sub(delta);
+                    self.replace(true);
+                    self.slab[idx as usize].value = Some(v);
+                    self.move_to(idx, T2);
+                }
+            }
+            return;
+        }
+
+        // a genuinely new key
+        if self.len[T1] + self.len[B1] == self.c {
+            if self.len[T1] < self.c {
+                self.drop_ghost(B1);
+                self.replace(false);
+            } else {
+                // B1 empty, T1 saturated: drop the LRU of T1 outright
+                let idx = self.head[T1];
+                self.detach(idx);
+                let key = &self.slab[idx as usize].key;
+                self.map.remove(key);
+                self.recycle(idx);
+            }
+        } else {
+            let total = self.len[T1] + self.len[T2] + self.len[B1] + self.len[B2];
+            if total >= self.c {
+                if total == 2 * self.c {
+                    self.drop_ghost(B2);
+                }
+                self.replace(false);
+            }
         }
 
-        self.recency_buckets.push_back((self.g, k.clone()));
-        self.items.insert(k, (v, self.g));
+        let idx = self.alloc(k.clone(), Some(v));
+        self.attach_tail(idx, T1);
+        self.map.insert(k, idx);
     }
 }
 
@@ -90,28 +766,145 @@ mod tests {
         let mut cache = Cache::initialize(3);
         cache.set(1, "a");
         let _ = cache.get(1);
-        println!("{:?}", cache.items);
-        println!("{:?}", cache.recency_buckets);
+        println!("{} {:?}", cache.table.len(), cache.slab);
         cache.set(2, "b");
         let _ = cache.get(2);
-        println!("{:?}", cache.items);
-        println!("{:?}", cache.recency_buckets);
+        println!("{} {:?}", cache.table.len(), cache.slab);
         cache.set(3, "c");
-        println!("{:?}", cache.items);
-        println!("{:?}", cache.recency_buckets);
+        println!("{} {:?}", cache.table.len(), cache.slab);
         let _ = cache.get(1);
-        println!("{:?}", cache.items);
-        println!("{:?}", cache.recency_buckets);
+        println!("{} {:?}", cache.table.len(), cache.slab);
         let _ = cache.get(2);
-        println!("{:?}", cache.items);
-        println!("{:?}", cache.recency_buckets);
+        println!("{} {:?}", cache.table.len(), cache.slab);
         cache.set(4, "d");
-        println!("{:?}", cache.items);
-        println!("{:?}", cache.recency_buckets);
-        assert_eq!(cache.items.len(), cache.max as usize);
+        println!("{} {:?}", cache.table.len(), cache.slab);
+        assert_eq!(cache.table.len(), cache.max as usize);
         assert!(cache.get(3).is_none());
     }
 
+    #[test]
+    fn purge_after_lru_reorder() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let now = Rc::new(Cell::new(0u64));
+        let clock = now.clone();
+        let mut cache =
+            Cache::initialize_with_ttl(4, 10).with_clock(Box::new(move || clock.get()));
+
+        cache.set(1, "a");
+        now.set(8);
+        cache.set(2, "b");
+        now.set(9);
+        // touch 1 while still fresh: it moves to the MRU end, stamp stays at 0
+        assert_eq!(cache.get(1).unwrap(), "a");
+        now.set(12);
+        // list head->tail is now 2 (fresh) then 1 (stale); the sweep must not
+        // stop at the fresh head and miss the stale entry behind it
+        cache.purge_expired();
+        assert!(cache.get(1).is_none());
+        assert_eq!(cache.get(2).unwrap(), "b");
+    }
+
+    #[cfg(feature = "disk")]
+    #[test]
+    fn spills_and_promotes() {
+        let dir = std::env::temp_dir().join("lru_spill_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut cache: Cache<u32, u64> = Cache::initialize_tiered(2, &dir).unwrap();
+        cache.set_tiered(1, 10);
+        cache.set_tiered(2, 20);
+        // inserting a third key evicts 1 to disk
+        cache.set_tiered(3, 30);
+        // a memory miss promotes 1 back from the spill tier
+        assert_eq!(cache.get_tiered(1), Some(10));
+    }
+
+    #[cfg(feature = "disk")]
+    #[test]
+    fn spill_survives_reopen() {
+        let dir = std::env::temp_dir().join("lru_spill_reopen_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        {
+            let mut cache: Cache<u32, u64> = Cache::initialize_tiered(2, &dir).unwrap();
+            cache.set_tiered(1, 10);
+            cache.set_tiered(2, 20);
+            // evict 1 to disk
+            cache.set_tiered(3, 30);
+        }
+        // a fresh store over the same directory must not wipe the bucket files
+        let mut reopened: Cache<u32, u64> = Cache::initialize_tiered(2, &dir).unwrap();
+        assert_eq!(reopened.get_tiered(1), Some(10));
+    }
+
+    #[test]
+    fn array_cache_evicts_lru() {
+        let mut cache: ArrayCache<u32, &str, 3> = ArrayCache::initialize(3);
+        cache.set(1, "a");
+        cache.set(2, "b");
+        cache.set(3, "c");
+        // touch 1 so it becomes most-recently-used
+        assert_eq!(cache.get(1).unwrap(), "a");
+        // inserting a fourth key drops the LRU entry (2)
+        cache.set(4, "d");
+        assert!(cache.get(2).is_none());
+        assert_eq!(cache.get(1).unwrap(), "a");
+        assert_eq!(cache.get(4).unwrap(), "d");
+    }
+
+    #[test]
+    fn fifo_evicts_by_insertion_order() {
+        let mut cache = Cache::initialize_with_policy(3, Policy::Fifo);
+        cache.set(1, "a");
+        cache.set(2, "b");
+        cache.set(3, "c");
+        // reading 1 must not save it from eviction under FIFO
+        assert_eq!(cache.get(1).unwrap(), "a");
+        cache.set(4, "d");
+        assert!(cache.get(1).is_none());
+        assert_eq!(cache.get(2).unwrap(), "b");
+    }
+
+    #[test]
+    fn arc_promotes_frequent_keys() {
+        let mut cache = ArcCache::initialize_arc(2);
+        cache.set(1, "a");
+        cache.set(2, "b");
+        // touch 1 twice so it lands in T2 (frequent)
+        assert_eq!(cache.get(1).unwrap(), "a");
+        // inserting a cold key evicts the recency-only entry (2), not 1
+        cache.set(3, "c");
+        assert_eq!(cache.get(1).unwrap(), "a");
+        assert!(cache.get(2).is_none());
+    }
+
+    #[test]
+    fn expires_ttl() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let now = Rc::new(Cell::new(0u64));
+        let clock = now.clone();
+        let mut cache =
+            Cache::initialize_with_ttl(4, 10).with_clock(Box::new(move || clock.get()));
+
+        cache.set(1, "a");
+        now.set(5);
+        // still within the ttl
+        assert_eq!(cache.get(1).unwrap(), "a");
+        now.set(20);
+        // inserted at tick 0, so 20 - 0 > 10 expires it on access
+        assert!(cache.get(1).is_none());
+
+        cache.set(2, "b");
+        now.set(100);
+        cache.set(3, "c");
+        // 2 is stale, 3 is fresh; the sweep stops at 3
+        cache.purge_expired();
+        assert!(cache.get(2).is_none());
+        assert_eq!(cache.get(3).unwrap(), "c");
+    }
+
     // #[test]
     // fn expires_lru_bench() {
     //     let count = 100_000;
@@ -131,4 +924,4 @@ mod tests {
 // hashmap cache + recency queue, recency update on get O(log N), set: lookup to expire O(1)
     // figure out best swap_remove so we don't cause left shift of vec
 
-// hashmap cache + splay tree, recency update on get O(log N), set: lookup to expire O(1)
\ No newline at end of file
+// hashmap cache + intrusive index list (slab), recency update on get O(1), set: evict head O(1)